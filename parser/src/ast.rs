@@ -0,0 +1,54 @@
+use location::Located;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol<'a> {
+    pub ns: Option<&'a str>,
+    pub name: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword<'a> {
+    pub ns: Option<&'a str>,
+    pub name: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AST<'a> {
+    Root(Vec<Located<AST<'a>>>),
+    Symbol(Symbol<'a>),
+    Keyword(Keyword<'a>),
+    CharLiteral(char),
+    StringLiteral(&'a str),
+    IntegerLiteral(i64),
+    FloatLiteral(f64),
+    RegexLiteral(&'a str),
+    List(Vec<Located<AST<'a>>>),
+    Vector(Vec<Located<AST<'a>>>),
+    Map(Vec<Located<AST<'a>>>),
+    Set(Vec<Located<AST<'a>>>),
+    AnonymousFn(Vec<Located<AST<'a>>>),
+    Metadata(Box<Located<AST<'a>>>),
+    Quoted(Box<Located<AST<'a>>>),
+    SyntaxQuoted(Box<Located<AST<'a>>>),
+    Unquoted(Symbol<'a>),
+    UnquotedSplicing(Symbol<'a>),
+    AtomDeref(Symbol<'a>),
+    And,
+    /// A span of tokens that could not be parsed as a form, produced by the
+    /// error-recovery parsing path.
+    Error {
+        consumed_range: (usize, usize),
+        message: String,
+    },
+    /// `#?(:clj ... :cljs ...)` or, when `splicing` is set, `#?@(...)`.
+    ReaderConditional {
+        splicing: bool,
+        branches: Vec<(Keyword<'a>, Located<AST<'a>>)>,
+    },
+    /// A tagged literal such as `#inst "..."`, `#uuid "..."`, or
+    /// `#my.ns/Tag form`.
+    TaggedLiteral {
+        tag: Symbol<'a>,
+        form: Box<Located<AST<'a>>>,
+    },
+}
@@ -0,0 +1,5 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub range: (usize, usize),
+}
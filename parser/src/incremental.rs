@@ -0,0 +1,353 @@
+use crate::ast::AST;
+use crate::{is_closing_delimiter, is_opening_delimiter, parse_form, parse_root};
+use location::Located;
+
+/// A single textual edit: `range` (byte offsets into the *old* source) is
+/// replaced with `insert`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: (usize, usize),
+    pub insert: String,
+}
+
+impl TextEdit {
+    fn delta(&self) -> isize {
+        self.insert.len() as isize - (self.range.1 - self.range.0) as isize
+    }
+}
+
+/// Reparses `new_src` by reusing as much of `old` as possible. Finds the
+/// smallest `AST::List`/`Vector`/`Map`/`Set` node whose range fully contains
+/// `edit`, re-lexes and re-parses only that node's source slice, and
+/// splices the fresh subtree back into a clone of `old` with the ranges of
+/// all following siblings shifted by the edit's length delta. When the fast
+/// path's invariants don't hold for that node, each enclosing container is
+/// tried in turn, and a full `parse_root` is the last resort.
+pub fn reparse<'a>(old: &Located<AST<'a>>, edit: TextEdit, new_src: &'a str) -> Located<AST<'a>> {
+    let delta = edit.delta();
+    try_splice(old, &edit, delta, new_src).unwrap_or_else(|| full_reparse(new_src))
+}
+
+fn full_reparse<'a>(new_src: &'a str) -> Located<AST<'a>> {
+    let tokens = lexer::lex(new_src);
+    let (_, root) = parse_root(&tokens).expect("reparse: full parse of new_src failed");
+    Located {
+        range: (0, new_src.len()),
+        value: root,
+    }
+}
+
+fn range_contains(outer: (usize, usize), inner: (usize, usize)) -> bool {
+    outer.0 <= inner.0 && inner.1 <= outer.1
+}
+
+fn container_children<'a, 'b>(ast: &'b AST<'a>) -> Option<&'b Vec<Located<AST<'a>>>> {
+    match ast {
+        AST::Root(forms)
+        | AST::List(forms)
+        | AST::Vector(forms)
+        | AST::Map(forms)
+        | AST::Set(forms) => Some(forms),
+        _ => None,
+    }
+}
+
+fn is_delimited_container(ast: &AST) -> bool {
+    matches!(ast, AST::List(_) | AST::Vector(_) | AST::Map(_) | AST::Set(_))
+}
+
+fn rebuild_container<'a>(ast: &AST<'a>, new_children: Vec<Located<AST<'a>>>) -> AST<'a> {
+    match ast {
+        AST::Root(_) => AST::Root(new_children),
+        AST::List(_) => AST::List(new_children),
+        AST::Vector(_) => AST::Vector(new_children),
+        AST::Map(_) => AST::Map(new_children),
+        AST::Set(_) => AST::Set(new_children),
+        _ => unreachable!("rebuild_container called on a non-container AST node"),
+    }
+}
+
+fn try_splice<'a>(
+    node: &Located<AST<'a>>,
+    edit: &TextEdit,
+    delta: isize,
+    new_src: &'a str,
+) -> Option<Located<AST<'a>>> {
+    let children = container_children(&node.value)?;
+    for (i, child) in children.iter().enumerate() {
+        if !range_contains(child.range, edit.range) {
+            continue;
+        }
+        if let Some(new_child) = try_splice(child, edit, delta, new_src) {
+            return Some(splice_child(node, i, new_child, delta));
+        }
+        if is_delimited_container(&child.value) {
+            if let Some(new_child) = splice_here(child, edit, delta, new_src) {
+                return Some(splice_child(node, i, new_child, delta));
+            }
+        }
+        return None;
+    }
+    None
+}
+
+fn splice_child<'a>(
+    node: &Located<AST<'a>>,
+    index: usize,
+    new_child: Located<AST<'a>>,
+    delta: isize,
+) -> Located<AST<'a>> {
+    let children = container_children(&node.value).unwrap();
+    let mut new_children = children.clone();
+    new_children[index] = new_child;
+    for later in new_children.iter_mut().skip(index + 1) {
+        shift_range(later, delta);
+    }
+    Located {
+        range: (node.range.0, (node.range.1 as isize + delta) as usize),
+        value: rebuild_container(&node.value, new_children),
+    }
+}
+
+fn shift_range<'a>(node: &mut Located<AST<'a>>, delta: isize) {
+    node.range = (
+        (node.range.0 as isize + delta) as usize,
+        (node.range.1 as isize + delta) as usize,
+    );
+    match &mut node.value {
+        AST::Root(forms)
+        | AST::List(forms)
+        | AST::Vector(forms)
+        | AST::Map(forms)
+        | AST::Set(forms)
+        | AST::AnonymousFn(forms) => {
+            for form in forms.iter_mut() {
+                shift_range(form, delta);
+            }
+        }
+        AST::Metadata(form) | AST::Quoted(form) | AST::SyntaxQuoted(form) => {
+            shift_range(form, delta)
+        }
+        AST::TaggedLiteral { form, .. } => shift_range(form, delta),
+        AST::ReaderConditional { branches, .. } => {
+            for (_, form) in branches.iter_mut() {
+                shift_range(form, delta);
+            }
+        }
+        AST::Error { consumed_range, .. } => {
+            consumed_range.0 = (consumed_range.0 as isize + delta) as usize;
+            consumed_range.1 = (consumed_range.1 as isize + delta) as usize;
+        }
+        _ => {}
+    }
+}
+
+fn offset_range<'a>(node: Located<AST<'a>>, offset: usize) -> Located<AST<'a>> {
+    let value = match node.value {
+        AST::Root(forms) => AST::Root(offset_forms(forms, offset)),
+        AST::List(forms) => AST::List(offset_forms(forms, offset)),
+        AST::Vector(forms) => AST::Vector(offset_forms(forms, offset)),
+        AST::Map(forms) => AST::Map(offset_forms(forms, offset)),
+        AST::Set(forms) => AST::Set(offset_forms(forms, offset)),
+        AST::AnonymousFn(forms) => AST::AnonymousFn(offset_forms(forms, offset)),
+        AST::Metadata(form) => AST::Metadata(Box::new(offset_range(*form, offset))),
+        AST::Quoted(form) => AST::Quoted(Box::new(offset_range(*form, offset))),
+        AST::SyntaxQuoted(form) => AST::SyntaxQuoted(Box::new(offset_range(*form, offset))),
+        AST::TaggedLiteral { tag, form } => AST::TaggedLiteral {
+            tag,
+            form: Box::new(offset_range(*form, offset)),
+        },
+        AST::ReaderConditional { splicing, branches } => AST::ReaderConditional {
+            splicing,
+            branches: branches
+                .into_iter()
+                .map(|(keyword, form)| (keyword, offset_range(form, offset)))
+                .collect(),
+        },
+        AST::Error {
+            consumed_range,
+            message,
+        } => AST::Error {
+            consumed_range: (consumed_range.0 + offset, consumed_range.1 + offset),
+            message,
+        },
+        other => other,
+    };
+    Located {
+        range: (node.range.0 + offset, node.range.1 + offset),
+        value,
+    }
+}
+
+fn offset_forms<'a>(forms: Vec<Located<AST<'a>>>, offset: usize) -> Vec<Located<AST<'a>>> {
+    forms.into_iter().map(|f| offset_range(f, offset)).collect()
+}
+
+/// Re-lexes and re-parses just `node`'s source slice, re-based at `node`'s
+/// own offset. Returns `None` if any fast-path invariant is violated, in
+/// which case the caller should fall back further (or all the way to a
+/// full reparse).
+fn splice_here<'a>(
+    node: &Located<AST<'a>>,
+    edit: &TextEdit,
+    delta: isize,
+    new_src: &'a str,
+) -> Option<Located<AST<'a>>> {
+    // Don't attempt the fast path if the edit touches the node's own
+    // opening/closing delimiter, or inserts a character that could change
+    // token boundaries outside the block (a new string/comment/regex
+    // delimiter).
+    if edit.range.0 <= node.range.0 || edit.range.1 >= node.range.1.saturating_sub(1) {
+        return None;
+    }
+    if edit.insert.contains(['"', ';', '#']) {
+        return None;
+    }
+
+    let new_end = (node.range.1 as isize + delta) as usize;
+    let new_slice = &new_src[node.range.0..new_end];
+    let tokens = lexer::lex(new_slice);
+    if !has_balanced_delimiters(&tokens) {
+        return None;
+    }
+
+    let (rest, new_form) = parse_form(&tokens).ok()?;
+    if !rest.is_empty() {
+        return None;
+    }
+    Some(offset_range(new_form, node.range.0))
+}
+
+fn has_balanced_delimiters(tokens: &[Located<lexer::Token>]) -> bool {
+    let mut depth = 0isize;
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        if is_opening_delimiter(&rest[0..1]) {
+            depth += 1;
+        } else if is_closing_delimiter(&rest[0..1]) {
+            depth -= 1;
+            if depth < 0 {
+                return false;
+            }
+        }
+        rest = &rest[1..];
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Keyword;
+
+    fn parse(src: &str) -> Located<AST> {
+        let tokens = lexer::lex(src);
+        let (_, root) = parse_root(&tokens).expect("test source must parse");
+        Located {
+            range: (0, src.len()),
+            value: root,
+        }
+    }
+
+    #[test]
+    fn splices_an_edit_fully_inside_a_nested_child() {
+        // The edit lands on the `2` inside the inner `(+ 1 2)`. A parsed
+        // form's range runs on through the token that follows it, so the
+        // inner list's range also covers the outer `)`: its re-lexed slice
+        // is unbalanced, `splice_here` declines it, and the splice happens
+        // one level up, on the outer list.
+        let src = "(defn foo (+ 1 2))";
+        let old = parse(src);
+        let edit = TextEdit {
+            range: (15, 16),
+            insert: "3".to_owned(),
+        };
+        let new_src = "(defn foo (+ 1 3))";
+
+        assert_eq!(reparse(&old, edit, new_src), parse(new_src));
+    }
+
+    #[test]
+    fn splices_the_parent_for_an_edit_in_the_gap_between_siblings() {
+        // A parsed form's range runs on through the token that follows it,
+        // so `foo`'s range reaches the end of `bar` and contains the edit.
+        // `foo` is not a container, so `try_splice` gives up at that level
+        // and `splice_here` re-parses the enclosing list instead.
+        let src = "(foo  bar)";
+        let old = parse(src);
+        let edit = TextEdit {
+            range: (4, 5),
+            insert: String::new(),
+        };
+        let new_src = "(foo bar)";
+
+        assert_eq!(reparse(&old, edit, new_src), parse(new_src));
+    }
+
+    #[test]
+    fn falls_back_to_full_reparse_for_an_edit_touching_a_delimiter() {
+        let src = "(foo)";
+        let old = parse(src);
+        let edit = TextEdit {
+            range: (4, 4),
+            insert: " bar".to_owned(),
+        };
+        let new_src = "(foo bar)";
+
+        assert_eq!(reparse(&old, edit, new_src), parse(new_src));
+    }
+
+    #[test]
+    fn shifts_nested_error_and_reader_conditional_ranges_of_later_siblings() {
+        let mut node = Located {
+            range: (10, 30),
+            value: AST::List(vec![
+                Located {
+                    range: (11, 16),
+                    value: AST::Error {
+                        consumed_range: (11, 16),
+                        message: "unparsable form".to_owned(),
+                    },
+                },
+                Located {
+                    range: (17, 29),
+                    value: AST::ReaderConditional {
+                        splicing: false,
+                        branches: vec![(
+                            Keyword {
+                                ns: None,
+                                name: "clj",
+                            },
+                            Located {
+                                range: (22, 28),
+                                value: AST::And,
+                            },
+                        )],
+                    },
+                },
+            ]),
+        };
+
+        shift_range(&mut node, 5);
+
+        assert_eq!(node.range, (15, 35));
+        let AST::List(children) = &node.value else {
+            panic!("expected List");
+        };
+
+        assert_eq!(children[0].range, (16, 21));
+        assert!(matches!(
+            &children[0].value,
+            AST::Error {
+                consumed_range: (16, 21),
+                ..
+            }
+        ));
+
+        assert_eq!(children[1].range, (22, 34));
+        let AST::ReaderConditional { branches, .. } = &children[1].value else {
+            panic!("expected ReaderConditional");
+        };
+        assert_eq!(branches[0].1.range, (27, 33));
+    }
+}
@@ -1,6 +1,15 @@
 pub mod ast;
+pub mod diagnostic;
+pub mod incremental;
+pub mod lossless;
+pub mod query;
+pub mod visit;
 
 pub use ast::AST;
+pub use diagnostic::Diagnostic;
+pub use incremental::{reparse, TextEdit};
+pub use lossless::{parse_root_lossless, to_source, LosslessForm, LosslessRoot, Trivia};
+pub use query::Query;
 use lexer::Token;
 use location::{Located};
 use token_combinator::{
@@ -89,25 +98,33 @@ fn parse_and(tokens: Tokens) -> ParseResult {
     located(map(and, |_| AST::And))(tokens)
 }
 
+fn to_keyword(keyword_str: &str) -> ast::Keyword<'_> {
+    let name = if keyword_str.starts_with("::") {
+        &keyword_str[2..]
+    } else {
+        &keyword_str[1..]
+    };
+    let splited = name.split('/').collect::<Vec<_>>();
+    if splited.len() == 1 {
+        let name = splited[0];
+        ast::Keyword { ns: None, name }
+    } else if splited.len() == 2 {
+        let ns = splited[0];
+        let name = splited[1];
+        ast::Keyword { name, ns: Some(ns) }
+    } else {
+        unreachable!()
+    }
+}
+
 fn parse_keyword(tokens: Tokens) -> ParseResult {
-    located(map(keyword, |keyword_str| {
-        let name = if keyword_str.starts_with("::") {
-            &keyword_str[2..]
-        } else {
-            &keyword_str[1..]
-        };
-        let splited = name.split('/').collect::<Vec<_>>();
-        if splited.len() == 1 {
-            let name = splited[0];
-            return AST::Keyword(ast::Keyword { ns: None, name });
-        } else if splited.len() == 2 {
-            let ns = splited[0];
-            let name = splited[1];
-            return AST::Keyword(ast::Keyword { name, ns: Some(ns) });
-        } else {
-            unreachable!()
-        }
-    }))(tokens)
+    located(map(keyword, |keyword_str| AST::Keyword(to_keyword(keyword_str))))(tokens)
+}
+
+fn parse_raw_keyword<'a>(
+    tokens: Tokens<'a>,
+) -> TokenParseResult<'a, Located<Token<'a>>, ast::Keyword<'a>> {
+    map(keyword, to_keyword)(tokens)
 }
 
 fn parse_char_literal(tokens: Tokens) -> ParseResult {
@@ -179,6 +196,48 @@ fn parse_anonymous_fn(tokens: Tokens) -> ParseResult {
     }))(tokens)
 }
 
+fn parse_reader_conditional(tokens: Tokens) -> ParseResult {
+    located(map(
+        preceded(
+            tuple((sharp, question)),
+            delimited(l_paren, many0(tuple((parse_raw_keyword, parse_form))), r_paren),
+        ),
+        |branches| AST::ReaderConditional {
+            splicing: false,
+            branches,
+        },
+    ))(tokens)
+}
+
+fn parse_reader_conditional_splicing(tokens: Tokens) -> ParseResult {
+    located(map(
+        preceded(
+            tuple((sharp, question_at)),
+            delimited(l_paren, many0(tuple((parse_raw_keyword, parse_form))), r_paren),
+        ),
+        |branches| AST::ReaderConditional {
+            splicing: true,
+            branches,
+        },
+    ))(tokens)
+}
+
+fn parse_tagged_literal(tokens: Tokens) -> ParseResult {
+    located(map(
+        preceded(sharp, tuple((parse_symbol, parse_form))),
+        |(tag, form)| {
+            if let AST::Symbol(tag) = tag.value {
+                AST::TaggedLiteral {
+                    tag,
+                    form: Box::new(form),
+                }
+            } else {
+                unreachable!()
+            }
+        },
+    ))(tokens)
+}
+
 fn parse_quoted_form(tokens: Tokens) -> ParseResult {
     located(map(preceded(quote, parse_form), |form| {
         AST::Quoted(Box::new(form))
@@ -202,8 +261,11 @@ pub fn parse_form(tokens: Tokens) -> ParseResult {
         parse_list,
         parse_vector,
         parse_map,
+        parse_reader_conditional_splicing,
+        parse_reader_conditional,
         parse_set,
         parse_regex_literal,
+        parse_tagged_literal,
         parse_anonymous_fn,
         parse_metadata,
         parse_and,
@@ -233,3 +295,251 @@ pub fn parse_root(tokens: Tokens) -> NotLocatedParseResult {
     }
     Ok((rest, AST::Root(forms)))
 }
+
+pub(crate) fn is_opening_delimiter(token: Tokens) -> bool {
+    l_paren(token).is_ok() || l_bracket(token).is_ok() || l_brace(token).is_ok()
+}
+
+pub(crate) fn is_closing_delimiter(token: Tokens) -> bool {
+    r_paren(token).is_ok() || r_bracket(token).is_ok() || r_brace(token).is_ok()
+}
+
+/// Consumes tokens until the matching closing delimiter of whatever opening
+/// delimiter was just entered, or until a closing delimiter that belongs to
+/// an enclosing form is reached (in which case that token is left for the
+/// caller). Returns the remaining tokens and how many were consumed.
+fn synchronize(tokens: Tokens) -> (Tokens, usize) {
+    let mut depth = 0usize;
+    let mut consumed = 0usize;
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        if is_closing_delimiter(&rest[0..1]) {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+            if depth == 0 {
+                rest = &rest[1..];
+                consumed += 1;
+                break;
+            }
+        } else if is_opening_delimiter(&rest[0..1]) {
+            depth += 1;
+        }
+        rest = &rest[1..];
+        consumed += 1;
+    }
+    (rest, consumed)
+}
+
+fn parse_list_recovering(tokens: Tokens) -> ParseResult {
+    located(map(
+        delimited(l_paren, many0(parse_form_recovering), r_paren),
+        |forms| AST::List(forms),
+    ))(tokens)
+}
+
+fn parse_vector_recovering(tokens: Tokens) -> ParseResult {
+    located(map(
+        delimited(l_bracket, many0(parse_form_recovering), r_bracket),
+        |forms| AST::Vector(forms),
+    ))(tokens)
+}
+
+fn parse_map_recovering(tokens: Tokens) -> ParseResult {
+    located(map_res(
+        delimited(l_brace, many0(parse_form_recovering), r_brace),
+        |res| match res {
+            Ok((rest, kvs)) => {
+                if kvs.len() % 2 != 0 {
+                    return Err(TokenParseError {
+                        errors: vec![TokenParseErrorKind::Other(
+                            "map must have even number of forms".to_owned(),
+                        )],
+                        tokens_consumed: kvs.len(),
+                    });
+                }
+                Ok((rest, AST::Map(kvs)))
+            }
+            Err(err) => Err(err),
+        },
+    ))(tokens)
+}
+
+fn parse_set_recovering(tokens: Tokens) -> ParseResult {
+    located(map(
+        tuple((sharp, delimited(l_brace, many0(parse_form_recovering), r_brace))),
+        |(_, forms)| AST::Set(forms),
+    ))(tokens)
+}
+
+/// Same alternation as `parse_form`, but the delimited collection forms
+/// recurse through their `_recovering` counterparts so a failure nested
+/// inside a list/vector/map/set is recovered in place instead of bubbling
+/// all the way up to `parse_root_recovering`.
+fn parse_form_inner_recovering(tokens: Tokens) -> ParseResult {
+    alt((
+        parse_symbol,
+        parse_keyword,
+        parse_char_literal,
+        parse_string_literal,
+        parse_integer_literal,
+        parse_float_literal,
+        parse_list_recovering,
+        parse_vector_recovering,
+        parse_map_recovering,
+        parse_reader_conditional_splicing,
+        parse_reader_conditional,
+        parse_set_recovering,
+        parse_regex_literal,
+        parse_tagged_literal,
+        parse_anonymous_fn,
+        parse_metadata,
+        parse_and,
+        parse_atom_deref,
+        parse_quoted_form,
+        parse_unquoted_symbol,
+        parse_unquoted_splicing_symbol,
+        parse_syntax_quoted_form,
+    ))(tokens)
+}
+
+/// Like `parse_form`, but an unparsable span of tokens is consumed (see
+/// `synchronize`) and replaced with an `AST::Error` node instead of
+/// returning `Err`. It still returns `Err` when `tokens` is empty or starts
+/// with a closing delimiter, which is left for the caller.
+pub fn parse_form_recovering(tokens: Tokens) -> ParseResult {
+    match parse_form_inner_recovering(tokens) {
+        Ok(result) => Ok(result),
+        Err(err) => {
+            if tokens.is_empty() || is_closing_delimiter(&tokens[0..1]) {
+                return Err(err);
+            }
+            let from = tokens[0].range;
+            let (rest, consumed) = synchronize(tokens);
+            let to = tokens[consumed - 1].range;
+            Ok((
+                rest,
+                Located {
+                    range: (from.0, to.1),
+                    value: AST::Error {
+                        consumed_range: (from.0, to.1),
+                        message: "unparsable form".to_owned(),
+                    },
+                },
+            ))
+        }
+    }
+}
+
+/// A `Visit`-based collector that records every `AST::Error` node reachable
+/// from the forms it's walked over, however deeply nested. Used by
+/// `parse_root_recovering` so an error recovered inside a list/vector body
+/// isn't missed just because the enclosing form parsed successfully.
+struct ErrorCollector {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> visit::Visit<'a> for ErrorCollector {
+    fn visit_error(&mut self, consumed_range: &(usize, usize), message: &str) {
+        self.diagnostics.push(Diagnostic {
+            message: message.to_owned(),
+            range: *consumed_range,
+        });
+    }
+}
+
+/// Error-recovery variant of `parse_root`: rather than stopping at the
+/// first `TokenParseError`, it synchronizes past the offending tokens,
+/// records a `Diagnostic`, and keeps parsing the rest of the buffer. This
+/// lets editor/lint tooling surface every error in a file in a single pass
+/// -- including errors recovered inside a list/vector/map/set body, not
+/// just ones at the top level. A stray closing delimiter at the top level,
+/// and a `#_`-discarded form that fails to parse, are skipped without a
+/// diagnostic.
+pub fn parse_root_recovering(tokens: Tokens) -> (AST, Vec<Diagnostic>) {
+    let mut rest = tokens;
+    let mut forms = Vec::new();
+    while !rest.is_empty() {
+        let (rest_tokens, comment_out_count) =
+            many0_count(sharp_underescore)(rest).unwrap_or((rest, 0));
+        rest = rest_tokens;
+        for _ in 0..comment_out_count {
+            if !rest.is_empty() {
+                match parse_form(rest) {
+                    Ok((rest_tokens, _)) => rest = rest_tokens,
+                    Err(_) => {
+                        let (rest_tokens, _) = synchronize(rest);
+                        rest = rest_tokens;
+                    }
+                }
+            }
+        }
+        if rest.is_empty() {
+            break;
+        }
+        match parse_form_recovering(rest) {
+            Ok((rest_tokens, form)) => {
+                rest = rest_tokens;
+                forms.push(form);
+            }
+            Err(_) => {
+                // A stray closing delimiter at the top level: skip it so we
+                // keep making progress instead of looping forever.
+                rest = &rest[1..];
+            }
+        }
+    }
+    let mut collector = ErrorCollector {
+        diagnostics: Vec::new(),
+    };
+    visit::walk_forms(&mut collector, &forms);
+    (AST::Root(forms), collector.diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tagged_literals() {
+        let tokens = lexer::lex(r#"#inst "2024-01-01""#);
+        let (rest, form) = parse_form(&tokens).unwrap();
+        assert!(rest.is_empty());
+        match form.value {
+            AST::TaggedLiteral { tag, form } => {
+                assert_eq!(tag.ns, None);
+                assert_eq!(tag.name, "inst");
+                assert!(matches!(form.value, AST::StringLiteral("2024-01-01")));
+            }
+            other => panic!("expected TaggedLiteral, got {other:?}"),
+        }
+
+        let tokens = lexer::lex("#my.ns/Tag form");
+        let (rest, form) = parse_form(&tokens).unwrap();
+        assert!(rest.is_empty());
+        match form.value {
+            AST::TaggedLiteral { tag, .. } => {
+                assert_eq!(tag.ns, Some("my.ns"));
+                assert_eq!(tag.name, "Tag");
+            }
+            other => panic!("expected TaggedLiteral, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn collects_diagnostics_for_errors_buried_inside_a_list() {
+        // The map literal has an odd number of forms, which is unparsable;
+        // it's nested inside `(defn foo ...)`, not at the top level, so the
+        // outer form recovers as a valid `List`, not an `AST::Error` -- the
+        // diagnostic has to come from walking the list's children.
+        let tokens = lexer::lex("(defn foo {:a 1 :b})");
+        let (root, diagnostics) = parse_root_recovering(&tokens);
+
+        assert_eq!(diagnostics.len(), 1);
+        match root {
+            AST::Root(forms) => assert!(matches!(forms[0].value, AST::List(_))),
+            _ => panic!("expected Root"),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use crate::ast::AST;
+use crate::parse_form;
+use lexer::token::parser::sharp_underescore;
+use lexer::Token;
+use location::Located;
+
+type Tokens<'a> = &'a [Located<Token<'a>>];
+
+/// A run of source text that carries no semantic meaning on its own:
+/// whitespace, a line comment, or a `#_`-discarded form (kept verbatim,
+/// marker included, so `to_source` can reproduce it byte for byte).
+///
+/// Like `parse_root`'s own `#_` handling, only *top-level* discards are
+/// recognized as trivia here — `parse_form` (used for a list/vector/map/set's
+/// children) has no case for a bare `#_` token, so a discard nested inside
+/// a container isn't supported at all and fails to parse, the same
+/// pre-existing limitation `parse_root` already has for nested `#_`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia<'a> {
+    Whitespace(&'a str),
+    Comment(&'a str),
+    Discarded(&'a str),
+    /// A `#_` marker whose discarded form is missing or unparsable (e.g. the
+    /// buffer ends right after `#_`, a normal in-progress editor state), or
+    /// a top-level form that fails to parse. Everything from there to the
+    /// end of the buffer is kept verbatim rather than dropped, so
+    /// `to_source` still round-trips.
+    Dangling(&'a str),
+}
+
+/// A top-level form together with the trivia that preceded it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessForm<'a> {
+    pub leading: Vec<Trivia<'a>>,
+    pub form: Located<AST<'a>>,
+    end: usize,
+}
+
+/// A lossless parse of a whole buffer: every top-level form plus whatever
+/// trivia trails the last one. Round-trips through `to_source`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessRoot<'a> {
+    pub forms: Vec<LosslessForm<'a>>,
+    pub trailing: Vec<Trivia<'a>>,
+}
+
+fn scan_gap(gap: &str) -> Vec<Trivia> {
+    let mut trivia = Vec::new();
+    let bytes = gap.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i] == b';' {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            trivia.push(Trivia::Comment(&gap[start..i]));
+        } else {
+            while i < bytes.len() && (bytes[i] as char).is_whitespace() {
+                i += 1;
+            }
+            if i == start {
+                // Not whitespace or a comment: stop rather than loop forever
+                // on something we don't understand.
+                break;
+            }
+            trivia.push(Trivia::Whitespace(&gap[start..i]));
+        }
+    }
+    trivia
+}
+
+/// Parses `tokens` (lexed from `src`) into a `LosslessRoot`: every gap
+/// between tokens is captured as `Trivia` rather than thrown away, so the
+/// original bytes can be reconstructed with `to_source`.
+pub fn parse_root_lossless<'a>(src: &'a str, tokens: Tokens<'a>) -> LosslessRoot<'a> {
+    let mut rest = tokens;
+    let mut cursor = 0usize;
+    let mut forms = Vec::new();
+
+    loop {
+        let mut leading = Vec::new();
+        while !rest.is_empty() {
+            leading.extend(scan_gap(&src[cursor..rest[0].range.0]));
+            cursor = rest[0].range.0;
+            let marker_start = cursor;
+            let after_marker = match sharp_underescore(rest) {
+                Ok((after_marker, _)) => after_marker,
+                Err(_) => break,
+            };
+            if after_marker.is_empty() {
+                // A dangling `#_` with nothing after it (e.g. the buffer
+                // ends mid-edit): nothing to discard, so stop rather than
+                // calling `parse_form` on an empty token slice. Keep the
+                // marker itself instead of dropping it from the output.
+                leading.push(Trivia::Dangling(&src[marker_start..src.len()]));
+                return LosslessRoot {
+                    forms,
+                    trailing: leading,
+                };
+            }
+            match parse_form(after_marker) {
+                Ok((after_form, _discarded)) => {
+                    let consumed = after_marker.len() - after_form.len();
+                    cursor = after_marker[consumed - 1].range.1;
+                    leading.push(Trivia::Discarded(&src[marker_start..cursor]));
+                    rest = after_form;
+                }
+                Err(_) => {
+                    // The form after `#_` failed to parse: the rest of the
+                    // buffer can't be split into trivia and forms, so keep
+                    // it verbatim as trailing text instead of losing it.
+                    leading.push(Trivia::Dangling(&src[marker_start..src.len()]));
+                    return LosslessRoot {
+                        forms,
+                        trailing: leading,
+                    };
+                }
+            }
+        }
+
+        if rest.is_empty() {
+            leading.extend(scan_gap(&src[cursor..src.len()]));
+            return LosslessRoot {
+                forms,
+                trailing: leading,
+            };
+        }
+
+        match parse_form(rest) {
+            Ok((after_form, form)) => {
+                let consumed = rest.len() - after_form.len();
+                let end = rest[consumed - 1].range.1;
+                rest = after_form;
+                cursor = end;
+                forms.push(LosslessForm { leading, form, end });
+            }
+            Err(_) => {
+                leading.push(Trivia::Dangling(&src[cursor..src.len()]));
+                return LosslessRoot {
+                    forms,
+                    trailing: leading,
+                };
+            }
+        }
+    }
+}
+
+/// Reproduces the exact source bytes a `LosslessRoot` was parsed from.
+pub fn to_source(root: &LosslessRoot, src: &str) -> String {
+    let mut out = String::new();
+    for form in &root.forms {
+        for trivia in &form.leading {
+            push_trivia(&mut out, trivia);
+        }
+        out.push_str(&src[form.form.range.0..form.end]);
+    }
+    for trivia in &root.trailing {
+        push_trivia(&mut out, trivia);
+    }
+    out
+}
+
+fn push_trivia(out: &mut String, trivia: &Trivia) {
+    match trivia {
+        Trivia::Whitespace(text)
+        | Trivia::Comment(text)
+        | Trivia::Discarded(text)
+        | Trivia::Dangling(text) => out.push_str(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(src: &str) {
+        let tokens = lexer::lex(src);
+        let root = parse_root_lossless(src, &tokens);
+        assert_eq!(to_source(&root, src), src);
+    }
+
+    #[test]
+    fn roundtrips_whitespace_and_comments() {
+        roundtrip("  (defn  foo [x] x)  ; trailing comment\n");
+    }
+
+    #[test]
+    fn roundtrips_top_level_discarded_forms() {
+        // `#_` is only recognized as trivia at the top level (see the
+        // `Trivia` doc comment) -- a discard nested inside `(+ 1 #_x 2)`
+        // isn't supported, the same as in `parse_root`.
+        roundtrip("#_(debug-only)\n(+ 1 2)");
+    }
+
+    #[test]
+    fn roundtrips_a_dangling_discard_marker_at_eof() {
+        // A buffer that ends right after `#_` is a normal in-progress
+        // editor state; the marker must be kept, not silently dropped.
+        roundtrip("foo #_");
+    }
+
+    #[test]
+    fn roundtrips_a_discard_marker_followed_by_an_unparsable_form() {
+        roundtrip("foo #_(");
+    }
+}
@@ -0,0 +1,270 @@
+use crate::ast::AST;
+use location::Located;
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    Any,
+    List,
+    Vector,
+    Map,
+    Set,
+    Symbol,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Attribute {
+    Name(String),
+    Ns(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Position {
+    Any,
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    /// ` ` — match at any depth below the previous step.
+    Descendant,
+    /// `>` — match only direct children of the previous step.
+    Child,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    combinator: Combinator,
+    kind: NodeKind,
+    attribute: Option<Attribute>,
+    position: Position,
+}
+
+/// A compiled selector, ready to be run against a tree with `Query::run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+impl Query {
+    /// Compiles a selector such as `list > symbol:first` or
+    /// `keyword[ns="foo"]` into a `Query`.
+    pub fn compile(selector: &str) -> Result<Query, String> {
+        let mut steps = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        for token in selector.split_whitespace() {
+            if token == ">" {
+                combinator = Combinator::Child;
+                continue;
+            }
+            steps.push(parse_step(token, combinator)?);
+            combinator = Combinator::Descendant;
+        }
+        Ok(Query { steps })
+    }
+
+    /// Runs the query against `root`, returning every matching node (and,
+    /// via `Located::range`, its source span). The first step matches
+    /// `root` itself as well as its descendants, so e.g. `"list"` run
+    /// directly against a `List` node matches that node, not just ones
+    /// nested inside it.
+    pub fn run<'a, 'b>(&self, root: &'b Located<AST<'a>>) -> Vec<&'b Located<AST<'a>>> {
+        let mut current = vec![root];
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut next = Vec::new();
+            for node in current {
+                next.extend(step_candidates(node, step, i == 0));
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+fn parse_step(token: &str, combinator: Combinator) -> Result<Step, String> {
+    let mut rest = token;
+
+    let mut position = Position::Any;
+    if let Some(colon) = rest.find(':') {
+        let pos_str = &rest[colon + 1..];
+        position = match pos_str {
+            "first" => Position::First,
+            "last" => Position::Last,
+            other => return Err(format!("unknown positional selector `:{other}`")),
+        };
+        rest = &rest[..colon];
+    }
+
+    let mut attribute = None;
+    if let Some(bracket) = rest.find('[') {
+        if !rest.ends_with(']') {
+            return Err(format!("unterminated attribute predicate in `{token}`"));
+        }
+        attribute = Some(parse_attribute(&rest[bracket + 1..rest.len() - 1])?);
+        rest = &rest[..bracket];
+    }
+
+    let kind = match rest {
+        "*" => NodeKind::Any,
+        "list" => NodeKind::List,
+        "vector" => NodeKind::Vector,
+        "map" => NodeKind::Map,
+        "set" => NodeKind::Set,
+        "symbol" => NodeKind::Symbol,
+        "keyword" => NodeKind::Keyword,
+        other => return Err(format!("unknown node kind `{other}`")),
+    };
+
+    Ok(Step {
+        combinator,
+        kind,
+        attribute,
+        position,
+    })
+}
+
+fn parse_attribute(predicate: &str) -> Result<Attribute, String> {
+    let (key, value) = predicate
+        .split_once('=')
+        .ok_or_else(|| format!("malformed attribute predicate `{predicate}`"))?;
+    let value = value.trim_matches('"').to_owned();
+    match key {
+        "name" => Ok(Attribute::Name(value)),
+        "ns" => Ok(Attribute::Ns(value)),
+        other => Err(format!("unknown attribute `{other}`")),
+    }
+}
+
+fn children_of<'a, 'b>(ast: &'b AST<'a>) -> Vec<&'b Located<AST<'a>>> {
+    match ast {
+        AST::Root(forms)
+        | AST::List(forms)
+        | AST::Vector(forms)
+        | AST::Map(forms)
+        | AST::Set(forms)
+        | AST::AnonymousFn(forms) => forms.iter().collect(),
+        AST::Metadata(form) | AST::Quoted(form) | AST::SyntaxQuoted(form) => vec![form.as_ref()],
+        AST::TaggedLiteral { form, .. } => vec![form.as_ref()],
+        AST::ReaderConditional { branches, .. } => branches.iter().map(|(_, form)| form).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn node_matches(node: &AST, kind: &NodeKind, attribute: &Option<Attribute>) -> bool {
+    let kind_matches = matches!(
+        (kind, node),
+        (NodeKind::Any, _)
+            | (NodeKind::List, AST::List(_))
+            | (NodeKind::Vector, AST::Vector(_))
+            | (NodeKind::Map, AST::Map(_))
+            | (NodeKind::Set, AST::Set(_))
+            | (NodeKind::Symbol, AST::Symbol(_))
+            | (NodeKind::Keyword, AST::Keyword(_))
+    );
+    if !kind_matches {
+        return false;
+    }
+    match attribute {
+        None => true,
+        Some(Attribute::Name(name)) => matches!(node, AST::Symbol(s) if s.name == name),
+        Some(Attribute::Ns(ns)) => match node {
+            AST::Symbol(s) => s.ns == Some(ns.as_str()),
+            AST::Keyword(k) => k.ns == Some(ns.as_str()),
+            _ => false,
+        },
+    }
+}
+
+fn collect_descendants<'a, 'b>(node: &'b Located<AST<'a>>, out: &mut Vec<&'b Located<AST<'a>>>) {
+    for child in children_of(&node.value) {
+        out.push(child);
+        collect_descendants(child, out);
+    }
+}
+
+fn apply_position<'a, 'b>(
+    mut matches: Vec<&'b Located<AST<'a>>>,
+    position: Position,
+) -> Vec<&'b Located<AST<'a>>> {
+    match position {
+        Position::Any => matches,
+        Position::First => matches.into_iter().take(1).collect(),
+        Position::Last => match matches.pop() {
+            Some(last) => vec![last],
+            None => Vec::new(),
+        },
+    }
+}
+
+fn step_candidates<'a, 'b>(
+    node: &'b Located<AST<'a>>,
+    step: &Step,
+    include_self: bool,
+) -> Vec<&'b Located<AST<'a>>> {
+    let mut pool = match step.combinator {
+        Combinator::Child => children_of(&node.value),
+        Combinator::Descendant => {
+            let mut out = Vec::new();
+            collect_descendants(node, &mut out);
+            out
+        }
+    };
+    if include_self {
+        pool.insert(0, node);
+    }
+    let matched = pool
+        .into_iter()
+        .filter(|candidate| node_matches(&candidate.value, &step.kind, &step.attribute))
+        .collect();
+    apply_position(matched, step.position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Symbol;
+
+    fn symbol<'a>(name: &'a str, range: (usize, usize)) -> Located<AST<'a>> {
+        Located {
+            range,
+            value: AST::Symbol(Symbol { ns: None, name }),
+        }
+    }
+
+    #[test]
+    fn finds_the_first_symbol_in_a_list() {
+        let root = Located {
+            range: (0, 18),
+            value: AST::List(vec![
+                symbol("defn", (1, 5)),
+                symbol("foo", (6, 9)),
+            ]),
+        };
+
+        let query = Query::compile("list > symbol:first").unwrap();
+        let found = query.run(&root);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].range, (1, 5));
+    }
+
+    #[test]
+    fn finds_symbols_by_name_anywhere_below_root() {
+        let root = Located {
+            range: (0, 0),
+            value: AST::Root(vec![
+                Located {
+                    range: (0, 0),
+                    value: AST::List(vec![symbol("defn", (0, 0)), symbol("foo", (0, 0))]),
+                },
+                symbol("defn", (0, 0)),
+            ]),
+        };
+
+        let query = Query::compile(r#"symbol[name="defn"]"#).unwrap();
+        let found = query.run(&root);
+
+        assert_eq!(found.len(), 2);
+    }
+}
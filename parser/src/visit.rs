@@ -0,0 +1,315 @@
+use crate::ast::{Keyword, Symbol, AST};
+use location::Located;
+
+/// Shared-reference walk over an `AST` tree. Every method has a default
+/// implementation that just recurses into children, so a visitor only
+/// needs to override the variants it cares about.
+pub trait Visit<'a> {
+    fn visit_ast(&mut self, node: &Located<AST<'a>>) {
+        walk_ast(self, node)
+    }
+    fn visit_root(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_symbol(&mut self, _symbol: &Symbol<'a>) {}
+    fn visit_keyword(&mut self, _keyword: &Keyword<'a>) {}
+    fn visit_char_literal(&mut self, _value: &char) {}
+    fn visit_string_literal(&mut self, _value: &&'a str) {}
+    fn visit_integer_literal(&mut self, _value: &i64) {}
+    fn visit_float_literal(&mut self, _value: &f64) {}
+    fn visit_regex_literal(&mut self, _value: &&'a str) {}
+    fn visit_list(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_vector(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_map(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_set(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_anonymous_fn(&mut self, forms: &[Located<AST<'a>>]) {
+        walk_forms(self, forms)
+    }
+    fn visit_metadata(&mut self, form: &Located<AST<'a>>) {
+        self.visit_ast(form)
+    }
+    fn visit_quoted(&mut self, form: &Located<AST<'a>>) {
+        self.visit_ast(form)
+    }
+    fn visit_syntax_quoted(&mut self, form: &Located<AST<'a>>) {
+        self.visit_ast(form)
+    }
+    fn visit_unquoted(&mut self, symbol: &Symbol<'a>) {
+        self.visit_symbol(symbol)
+    }
+    fn visit_unquoted_splicing(&mut self, symbol: &Symbol<'a>) {
+        self.visit_symbol(symbol)
+    }
+    fn visit_atom_deref(&mut self, symbol: &Symbol<'a>) {
+        self.visit_symbol(symbol)
+    }
+    fn visit_and(&mut self) {}
+    fn visit_error(&mut self, _consumed_range: &(usize, usize), _message: &str) {}
+    fn visit_reader_conditional(&mut self, _splicing: &bool, branches: &[(Keyword<'a>, Located<AST<'a>>)]) {
+        for (_, form) in branches {
+            self.visit_ast(form);
+        }
+    }
+    fn visit_tagged_literal(&mut self, _tag: &Symbol<'a>, form: &Located<AST<'a>>) {
+        self.visit_ast(form)
+    }
+}
+
+pub fn walk_ast<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, node: &Located<AST<'a>>) {
+    match &node.value {
+        AST::Root(forms) => visitor.visit_root(forms),
+        AST::Symbol(symbol) => visitor.visit_symbol(symbol),
+        AST::Keyword(keyword) => visitor.visit_keyword(keyword),
+        AST::CharLiteral(value) => visitor.visit_char_literal(value),
+        AST::StringLiteral(value) => visitor.visit_string_literal(value),
+        AST::IntegerLiteral(value) => visitor.visit_integer_literal(value),
+        AST::FloatLiteral(value) => visitor.visit_float_literal(value),
+        AST::RegexLiteral(value) => visitor.visit_regex_literal(value),
+        AST::List(forms) => visitor.visit_list(forms),
+        AST::Vector(forms) => visitor.visit_vector(forms),
+        AST::Map(forms) => visitor.visit_map(forms),
+        AST::Set(forms) => visitor.visit_set(forms),
+        AST::AnonymousFn(forms) => visitor.visit_anonymous_fn(forms),
+        AST::Metadata(form) => visitor.visit_metadata(form),
+        AST::Quoted(form) => visitor.visit_quoted(form),
+        AST::SyntaxQuoted(form) => visitor.visit_syntax_quoted(form),
+        AST::Unquoted(symbol) => visitor.visit_unquoted(symbol),
+        AST::UnquotedSplicing(symbol) => visitor.visit_unquoted_splicing(symbol),
+        AST::AtomDeref(symbol) => visitor.visit_atom_deref(symbol),
+        AST::And => visitor.visit_and(),
+        AST::Error {
+            consumed_range,
+            message,
+        } => visitor.visit_error(consumed_range, message),
+        AST::ReaderConditional { splicing, branches } => {
+            visitor.visit_reader_conditional(splicing, branches)
+        }
+        AST::TaggedLiteral { tag, form } => visitor.visit_tagged_literal(tag, form),
+    }
+}
+
+pub fn walk_forms<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, forms: &[Located<AST<'a>>]) {
+    for form in forms {
+        visitor.visit_ast(form);
+    }
+}
+
+/// Exclusive-reference walk over an `AST` tree, for visitors that rewrite
+/// nodes in place.
+pub trait VisitMut<'a> {
+    fn visit_ast_mut(&mut self, node: &mut Located<AST<'a>>) {
+        walk_ast_mut(self, node)
+    }
+    fn visit_root_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_symbol_mut(&mut self, _symbol: &mut Symbol<'a>) {}
+    fn visit_keyword_mut(&mut self, _keyword: &mut Keyword<'a>) {}
+    fn visit_char_literal_mut(&mut self, _value: &mut char) {}
+    fn visit_string_literal_mut(&mut self, _value: &mut &'a str) {}
+    fn visit_integer_literal_mut(&mut self, _value: &mut i64) {}
+    fn visit_float_literal_mut(&mut self, _value: &mut f64) {}
+    fn visit_regex_literal_mut(&mut self, _value: &mut &'a str) {}
+    fn visit_list_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_vector_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_map_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_set_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_anonymous_fn_mut(&mut self, forms: &mut [Located<AST<'a>>]) {
+        walk_forms_mut(self, forms)
+    }
+    fn visit_metadata_mut(&mut self, form: &mut Located<AST<'a>>) {
+        self.visit_ast_mut(form)
+    }
+    fn visit_quoted_mut(&mut self, form: &mut Located<AST<'a>>) {
+        self.visit_ast_mut(form)
+    }
+    fn visit_syntax_quoted_mut(&mut self, form: &mut Located<AST<'a>>) {
+        self.visit_ast_mut(form)
+    }
+    fn visit_unquoted_mut(&mut self, symbol: &mut Symbol<'a>) {
+        self.visit_symbol_mut(symbol)
+    }
+    fn visit_unquoted_splicing_mut(&mut self, symbol: &mut Symbol<'a>) {
+        self.visit_symbol_mut(symbol)
+    }
+    fn visit_atom_deref_mut(&mut self, symbol: &mut Symbol<'a>) {
+        self.visit_symbol_mut(symbol)
+    }
+    fn visit_and_mut(&mut self) {}
+    fn visit_error_mut(&mut self, _consumed_range: &mut (usize, usize), _message: &mut String) {}
+    fn visit_reader_conditional_mut(
+        &mut self,
+        _splicing: &mut bool,
+        branches: &mut [(Keyword<'a>, Located<AST<'a>>)],
+    ) {
+        for (_, form) in branches {
+            self.visit_ast_mut(form);
+        }
+    }
+    fn visit_tagged_literal_mut(&mut self, _tag: &mut Symbol<'a>, form: &mut Located<AST<'a>>) {
+        self.visit_ast_mut(form)
+    }
+}
+
+pub fn walk_ast_mut<'a, V: VisitMut<'a> + ?Sized>(visitor: &mut V, node: &mut Located<AST<'a>>) {
+    match &mut node.value {
+        AST::Root(forms) => visitor.visit_root_mut(forms),
+        AST::Symbol(symbol) => visitor.visit_symbol_mut(symbol),
+        AST::Keyword(keyword) => visitor.visit_keyword_mut(keyword),
+        AST::CharLiteral(value) => visitor.visit_char_literal_mut(value),
+        AST::StringLiteral(value) => visitor.visit_string_literal_mut(value),
+        AST::IntegerLiteral(value) => visitor.visit_integer_literal_mut(value),
+        AST::FloatLiteral(value) => visitor.visit_float_literal_mut(value),
+        AST::RegexLiteral(value) => visitor.visit_regex_literal_mut(value),
+        AST::List(forms) => visitor.visit_list_mut(forms),
+        AST::Vector(forms) => visitor.visit_vector_mut(forms),
+        AST::Map(forms) => visitor.visit_map_mut(forms),
+        AST::Set(forms) => visitor.visit_set_mut(forms),
+        AST::AnonymousFn(forms) => visitor.visit_anonymous_fn_mut(forms),
+        AST::Metadata(form) => visitor.visit_metadata_mut(form),
+        AST::Quoted(form) => visitor.visit_quoted_mut(form),
+        AST::SyntaxQuoted(form) => visitor.visit_syntax_quoted_mut(form),
+        AST::Unquoted(symbol) => visitor.visit_unquoted_mut(symbol),
+        AST::UnquotedSplicing(symbol) => visitor.visit_unquoted_splicing_mut(symbol),
+        AST::AtomDeref(symbol) => visitor.visit_atom_deref_mut(symbol),
+        AST::And => visitor.visit_and_mut(),
+        AST::Error {
+            consumed_range,
+            message,
+        } => visitor.visit_error_mut(consumed_range, message),
+        AST::ReaderConditional { splicing, branches } => {
+            visitor.visit_reader_conditional_mut(splicing, branches)
+        }
+        AST::TaggedLiteral { tag, form } => visitor.visit_tagged_literal_mut(tag, form),
+    }
+}
+
+pub fn walk_forms_mut<'a, V: VisitMut<'a> + ?Sized>(visitor: &mut V, forms: &mut [Located<AST<'a>>]) {
+    for form in forms {
+        visitor.visit_ast_mut(form);
+    }
+}
+
+/// Owned rewrite of an `AST` tree. Unlike `VisitMut`, a `Fold` can change a
+/// node's shape (e.g. replace a `Symbol` with a different one) rather than
+/// only mutating it in place.
+pub trait Fold<'a> {
+    fn fold_ast(&mut self, node: Located<AST<'a>>) -> Located<AST<'a>> {
+        fold_ast(self, node)
+    }
+    fn fold_symbol(&mut self, symbol: Symbol<'a>) -> Symbol<'a> {
+        symbol
+    }
+    fn fold_keyword(&mut self, keyword: Keyword<'a>) -> Keyword<'a> {
+        keyword
+    }
+}
+
+pub fn fold_ast<'a, F: Fold<'a> + ?Sized>(folder: &mut F, node: Located<AST<'a>>) -> Located<AST<'a>> {
+    let Located { range, value } = node;
+    let value = match value {
+        AST::Root(forms) => AST::Root(fold_forms(folder, forms)),
+        AST::Symbol(symbol) => AST::Symbol(folder.fold_symbol(symbol)),
+        AST::Keyword(keyword) => AST::Keyword(folder.fold_keyword(keyword)),
+        AST::CharLiteral(value) => AST::CharLiteral(value),
+        AST::StringLiteral(value) => AST::StringLiteral(value),
+        AST::IntegerLiteral(value) => AST::IntegerLiteral(value),
+        AST::FloatLiteral(value) => AST::FloatLiteral(value),
+        AST::RegexLiteral(value) => AST::RegexLiteral(value),
+        AST::List(forms) => AST::List(fold_forms(folder, forms)),
+        AST::Vector(forms) => AST::Vector(fold_forms(folder, forms)),
+        AST::Map(forms) => AST::Map(fold_forms(folder, forms)),
+        AST::Set(forms) => AST::Set(fold_forms(folder, forms)),
+        AST::AnonymousFn(forms) => AST::AnonymousFn(fold_forms(folder, forms)),
+        AST::Metadata(form) => AST::Metadata(Box::new(folder.fold_ast(*form))),
+        AST::Quoted(form) => AST::Quoted(Box::new(folder.fold_ast(*form))),
+        AST::SyntaxQuoted(form) => AST::SyntaxQuoted(Box::new(folder.fold_ast(*form))),
+        AST::Unquoted(symbol) => AST::Unquoted(folder.fold_symbol(symbol)),
+        AST::UnquotedSplicing(symbol) => AST::UnquotedSplicing(folder.fold_symbol(symbol)),
+        AST::AtomDeref(symbol) => AST::AtomDeref(folder.fold_symbol(symbol)),
+        AST::And => AST::And,
+        AST::Error {
+            consumed_range,
+            message,
+        } => AST::Error {
+            consumed_range,
+            message,
+        },
+        AST::ReaderConditional { splicing, branches } => AST::ReaderConditional {
+            splicing,
+            branches: branches
+                .into_iter()
+                .map(|(keyword, form)| (folder.fold_keyword(keyword), folder.fold_ast(form)))
+                .collect(),
+        },
+        AST::TaggedLiteral { tag, form } => AST::TaggedLiteral {
+            tag: folder.fold_symbol(tag),
+            form: Box::new(folder.fold_ast(*form)),
+        },
+    };
+    Located { range, value }
+}
+
+fn fold_forms<'a, F: Fold<'a> + ?Sized>(
+    folder: &mut F,
+    forms: Vec<Located<AST<'a>>>,
+) -> Vec<Located<AST<'a>>> {
+    forms.into_iter().map(|form| folder.fold_ast(form)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SymbolCounter {
+        count: usize,
+    }
+
+    impl<'a> Visit<'a> for SymbolCounter {
+        fn visit_symbol(&mut self, _symbol: &Symbol<'a>) {
+            self.count += 1;
+        }
+    }
+
+    fn sym(name: &str) -> Located<AST> {
+        Located {
+            range: (0, name.len()),
+            value: AST::Symbol(Symbol { ns: None, name }),
+        }
+    }
+
+    #[test]
+    fn counts_every_symbol_in_a_root() {
+        let root = Located {
+            range: (0, 0),
+            value: AST::Root(vec![
+                sym("defn"),
+                Located {
+                    range: (0, 0),
+                    value: AST::List(vec![sym("foo"), sym("bar")]),
+                },
+            ]),
+        };
+
+        let mut counter = SymbolCounter { count: 0 };
+        counter.visit_ast(&root);
+
+        assert_eq!(counter.count, 3);
+    }
+}